@@ -0,0 +1,328 @@
+use bitflags::bitflags;
+use gio::Cancellable;
+use glib::translate::*;
+use glib::Error;
+use ostree_sys as ffi;
+use std::os::unix::io::RawFd;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::{Repo, RepoDevInoCache};
+
+/// Ownership mode to use when checking out a tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepoCheckoutMode {
+    None,
+    User,
+}
+
+/// How to handle files that already exist at the checkout destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepoCheckoutOverwriteMode {
+    None,
+    UnionFiles,
+    UnionIdentical,
+    AddFiles,
+}
+
+bitflags! {
+    /// Composable checkout behaviors, layered over [`RepoCheckoutOverwriteMode`]
+    /// and the whiteout-handling flag, the way libgit2's `checkout_strategy`
+    /// bitfield replaced a scalar enum.
+    ///
+    /// Pass a combination to [`RepoCheckoutAtOptionsBuilder::strategy`]
+    /// instead of setting [`RepoCheckoutAtOptionsBuilder::overwrite_mode`]
+    /// and [`RepoCheckoutAtOptionsBuilder::force_copy_zerosized`]
+    /// separately; whiteout processing has no setter of its own.
+    #[derive(Default)]
+    pub struct RepoCheckoutStrategy: u32 {
+        /// Union arbitrary conflicting files, last writer wins.
+        const UNION_FILES = 0b0000_0001;
+        /// Union files that are already identical at the destination.
+        const UNION_IDENTICAL = 0b0000_0010;
+        /// Process whiteout markers, removing the shadowed destination entry.
+        const PROCESS_WHITEOUTS = 0b0000_0100;
+        /// Force a real copy even for zero-sized files.
+        const FORCE_COPY_ZEROSIZED = 0b0000_1000;
+    }
+}
+
+impl RepoCheckoutStrategy {
+    /// Fold the union-related bits down to the scalar
+    /// [`RepoCheckoutOverwriteMode`] the C API understands.
+    /// `UNION_IDENTICAL` takes precedence over `UNION_FILES`, matching the
+    /// precedence of the overwrite mode variants they replace.
+    fn overwrite_mode(self) -> RepoCheckoutOverwriteMode {
+        if self.contains(RepoCheckoutStrategy::UNION_IDENTICAL) {
+            RepoCheckoutOverwriteMode::UnionIdentical
+        } else if self.contains(RepoCheckoutStrategy::UNION_FILES) {
+            RepoCheckoutOverwriteMode::UnionFiles
+        } else {
+            RepoCheckoutOverwriteMode::None
+        }
+    }
+}
+
+/// The verdict returned by a [`RepoCheckoutFilter`] for a given path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepoCheckoutFilterResult {
+    Allow,
+    Skip,
+}
+
+type RepoCheckoutFilterFn = dyn Fn(&Repo, &Path, &libc::stat) -> RepoCheckoutFilterResult;
+
+/// A boxed callback invoked per-entry during a checkout to allow skipping paths.
+///
+/// Construct one with [`repo_checkout_filter`].
+#[derive(Clone)]
+pub struct RepoCheckoutFilter(Rc<RepoCheckoutFilterFn>);
+
+/// Wrap a closure as a [`RepoCheckoutFilter`] suitable for
+/// [`RepoCheckoutAtOptionsBuilder::filter`].
+pub fn repo_checkout_filter<F>(filter: F) -> RepoCheckoutFilter
+where
+    F: Fn(&Repo, &Path, &libc::stat) -> RepoCheckoutFilterResult + 'static,
+{
+    RepoCheckoutFilter(Rc::new(filter))
+}
+
+/// Options for [`Repo::checkout_at`].
+///
+/// Build one with [`RepoCheckoutAtOptions::builder`] rather than constructing
+/// this struct directly; the fields are kept private so new options can be
+/// added without breaking callers.
+pub struct RepoCheckoutAtOptions {
+    pub(crate) mode: RepoCheckoutMode,
+    pub(crate) overwrite_mode: RepoCheckoutOverwriteMode,
+    pub(crate) enable_fsync: bool,
+    pub(crate) force_copy: bool,
+    pub(crate) force_copy_zerosized: bool,
+    pub(crate) devino_to_csum_cache: Option<RepoDevInoCache>,
+    pub(crate) filter: Option<RepoCheckoutFilter>,
+    pub(crate) bareuseronly_dirs: bool,
+    pub(crate) subpath: Option<PathBuf>,
+    pub(crate) no_copy_fallback: bool,
+    pub(crate) process_whiteouts: bool,
+}
+
+impl Default for RepoCheckoutAtOptions {
+    fn default() -> Self {
+        RepoCheckoutAtOptions {
+            mode: RepoCheckoutMode::None,
+            overwrite_mode: RepoCheckoutOverwriteMode::None,
+            enable_fsync: false,
+            force_copy: false,
+            force_copy_zerosized: false,
+            devino_to_csum_cache: None,
+            filter: None,
+            bareuseronly_dirs: false,
+            subpath: None,
+            no_copy_fallback: false,
+            process_whiteouts: false,
+        }
+    }
+}
+
+impl RepoCheckoutAtOptions {
+    /// Start building a `RepoCheckoutAtOptions` via chained setters.
+    pub fn builder() -> RepoCheckoutAtOptionsBuilder {
+        RepoCheckoutAtOptionsBuilder::default()
+    }
+}
+
+/// Chainable builder for [`RepoCheckoutAtOptions`], following the same shape
+/// as git2-rs's `CheckoutBuilder`.
+#[derive(Default)]
+pub struct RepoCheckoutAtOptionsBuilder {
+    options: RepoCheckoutAtOptions,
+}
+
+impl RepoCheckoutAtOptionsBuilder {
+    pub fn mode(mut self, mode: RepoCheckoutMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    pub fn overwrite_mode(mut self, overwrite_mode: RepoCheckoutOverwriteMode) -> Self {
+        self.options.overwrite_mode = overwrite_mode;
+        self
+    }
+
+    pub fn enable_fsync(mut self, enable_fsync: bool) -> Self {
+        self.options.enable_fsync = enable_fsync;
+        self
+    }
+
+    pub fn force_copy(mut self, force_copy: bool) -> Self {
+        self.options.force_copy = force_copy;
+        self
+    }
+
+    pub fn force_copy_zerosized(mut self, force_copy_zerosized: bool) -> Self {
+        self.options.force_copy_zerosized = force_copy_zerosized;
+        self
+    }
+
+    /// Supply the dev/inode-to-checksum cache to populate and consult during
+    /// the checkout. Ownership is taken here so callers don't have to manage
+    /// the cache's lifetime separately from the options.
+    pub fn devino_cache(mut self, cache: RepoDevInoCache) -> Self {
+        self.options.devino_to_csum_cache = Some(cache);
+        self
+    }
+
+    /// Restrict the checkout to the entries for which `filter` returns
+    /// `Allow`. The closure is boxed internally, so no external lifetime
+    /// bookkeeping is required.
+    pub fn filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&Repo, &Path, &libc::stat) -> RepoCheckoutFilterResult + 'static,
+    {
+        self.options.filter = Some(repo_checkout_filter(filter));
+        self
+    }
+
+    pub fn bareuseronly_dirs(mut self, bareuseronly_dirs: bool) -> Self {
+        self.options.bareuseronly_dirs = bareuseronly_dirs;
+        self
+    }
+
+    /// Restrict the checkout to the tree found at `subpath` within the
+    /// commit, checking out its contents directly into the destination
+    /// instead of the whole commit root.
+    pub fn subpath(mut self, subpath: impl Into<PathBuf>) -> Self {
+        self.options.subpath = Some(subpath.into());
+        self
+    }
+
+    /// Disable the implicit fallback that copies checked-out content into
+    /// the repo's uncompressed-objects cache.
+    ///
+    /// By default a checkout may populate this cache so that later
+    /// operations on the same objects can reuse the uncompressed copy; for
+    /// server-side repos that get rsynced this is undesirable, since it
+    /// leaves extra files behind that aren't part of the commit and would
+    /// have to be excluded from the sync. Setting this neither reads from
+    /// nor writes to that cache, so the checkout has no side effects beyond
+    /// the destination directory.
+    ///
+    /// This is independent of [`Self::devino_cache`]: the devino cache is an
+    /// in-memory dev/inode-to-checksum map the caller owns and inspects
+    /// afterwards, while the uncompressed-objects cache is on-disk state
+    /// ostree itself manages under the repo directory.
+    pub fn no_copy_fallback(mut self, no_copy_fallback: bool) -> Self {
+        self.options.no_copy_fallback = no_copy_fallback;
+        self
+    }
+
+    /// Apply a combination of [`RepoCheckoutStrategy`] flags, covering
+    /// overwrite mode, whiteout processing and zero-sized-file copying in
+    /// one call instead of setting each independently. The existing
+    /// [`Self::overwrite_mode`] and [`Self::force_copy_zerosized`] setters
+    /// keep working as before; whichever is called last wins.
+    pub fn strategy(mut self, strategy: RepoCheckoutStrategy) -> Self {
+        self.options.overwrite_mode = strategy.overwrite_mode();
+        self.options.process_whiteouts = strategy.contains(RepoCheckoutStrategy::PROCESS_WHITEOUTS);
+        self.options.force_copy_zerosized =
+            strategy.contains(RepoCheckoutStrategy::FORCE_COPY_ZEROSIZED);
+        self
+    }
+
+    /// Finish building, producing the `RepoCheckoutAtOptions` to pass to
+    /// [`Repo::checkout_at`].
+    pub fn build(self) -> RepoCheckoutAtOptions {
+        self.options
+    }
+}
+
+impl Repo {
+    /// Check out `checksum` below `destination_dfd`/`destination_path`.
+    ///
+    /// Build `options` with [`RepoCheckoutAtOptions::builder`], calling
+    /// `.build()` at the call site — an `Option<impl Trait>` parameter here
+    /// would leave the compiler unable to infer a type for `None`.
+    pub fn checkout_at(
+        &self,
+        options: Option<RepoCheckoutAtOptions>,
+        destination_dfd: RawFd,
+        destination_path: impl AsRef<Path>,
+        checksum: &str,
+        cancellable: Option<&Cancellable>,
+    ) -> Result<(), Error> {
+        let destination_path = destination_path.as_ref();
+
+        unsafe {
+            let mut ffi_options: ffi::OstreeRepoCheckoutAtOptions = std::mem::zeroed();
+            // Keeps the subpath's CString alive for the duration of the call below.
+            let subpath_stash = options
+                .as_ref()
+                .and_then(|options| options.subpath.as_ref())
+                .map(|subpath| subpath.to_glib_none());
+            if let Some(ref options) = options {
+                ffi_options.mode = options.mode.to_glib();
+                ffi_options.overwrite_mode = options.overwrite_mode.to_glib();
+                ffi_options.enable_fsync = options.enable_fsync.to_glib();
+                ffi_options.force_copy = options.force_copy.to_glib();
+                ffi_options.force_copy_zerosized = options.force_copy_zerosized.to_glib();
+                ffi_options.bareuseronly_dirs = options.bareuseronly_dirs.to_glib();
+                ffi_options.no_copy_fallback = options.no_copy_fallback.to_glib();
+                ffi_options.process_whiteouts = options.process_whiteouts.to_glib();
+                if let Some(ref cache) = options.devino_to_csum_cache {
+                    ffi_options.devino_to_csum_cache = cache.to_glib_none().0;
+                }
+                if let Some(ref filter) = options.filter {
+                    ffi_options.filter = Some(checkout_filter_trampoline);
+                    // `Rc<RepoCheckoutFilterFn>` is a fat pointer and can't
+                    // round-trip through the thin `gpointer` the C API
+                    // expects, so box it once more to get a thin pointer.
+                    ffi_options.filter_user_data =
+                        Box::into_raw(Box::new(filter.0.clone())) as glib_sys::gpointer;
+                }
+                if let Some((subpath_ptr, _)) = subpath_stash {
+                    ffi_options.subpath = subpath_ptr;
+                }
+            }
+
+            let mut error = std::ptr::null_mut();
+            let is_ok = ffi::ostree_repo_checkout_at(
+                self.to_glib_none().0,
+                &mut ffi_options,
+                destination_dfd,
+                destination_path.to_glib_none().0,
+                checksum.to_glib_none().0,
+                cancellable.to_glib_none().0,
+                &mut error,
+            );
+
+            if options.as_ref().and_then(|o| o.filter.as_ref()).is_some() {
+                // The trampoline only borrows the filter for the duration of
+                // the call above; drop our extra strong reference again.
+                drop(Box::from_raw(
+                    ffi_options.filter_user_data as *mut Rc<RepoCheckoutFilterFn>,
+                ));
+            }
+
+            if is_ok == glib_sys::GFALSE {
+                Err(from_glib_full(error))
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn checkout_filter_trampoline(
+    repo: *mut ffi::OstreeRepo,
+    path: *const libc::c_char,
+    stat: *mut libc::stat,
+    user_data: glib_sys::gpointer,
+) -> ffi::OstreeRepoCheckoutFilterResult {
+    let filter = &*(user_data as *const Rc<RepoCheckoutFilterFn>);
+    let repo: Repo = from_glib_none(repo);
+    let path = Path::new(std::ffi::CStr::from_ptr(path).to_str().unwrap());
+    match (**filter)(&repo, path, &*stat) {
+        RepoCheckoutFilterResult::Allow => ffi::OSTREE_REPO_CHECKOUT_FILTER_ALLOW,
+        RepoCheckoutFilterResult::Skip => ffi::OSTREE_REPO_CHECKOUT_FILTER_SKIP,
+    }
+}
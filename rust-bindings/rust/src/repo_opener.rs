@@ -0,0 +1,103 @@
+use gio::Cancellable;
+use glib::Error;
+use std::path::Path;
+
+use crate::{Repo, RepoMode};
+
+/// Builder for opening an existing repo or initializing a new one,
+/// following the create-flag-plus-knobs shape of zbox's `RepoOpener`.
+///
+/// Every `TestRepo::new`-style helper otherwise reimplements the
+/// construct-then-`create`-or-`open` dance by hand; this centralizes it, and
+/// makes the default mode and any config overrides explicit in one place.
+pub struct RepoOpener {
+    mode: RepoMode,
+    create: bool,
+    fsync: Option<bool>,
+    config: Vec<(String, String)>,
+}
+
+impl Default for RepoOpener {
+    fn default() -> Self {
+        RepoOpener {
+            mode: RepoMode::BareUser,
+            create: false,
+            fsync: None,
+            config: Vec::new(),
+        }
+    }
+}
+
+impl RepoOpener {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The mode to initialize a newly created repo with. Ignored when
+    /// opening an existing one.
+    pub fn mode(mut self, mode: RepoMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// If true, initialize a repo at the target location instead of
+    /// requiring one to already exist there.
+    pub fn create(mut self, create: bool) -> Self {
+        self.create = create;
+        self
+    }
+
+    /// Set the repo's `core.fsync` policy as part of opening it.
+    pub fn fsync(mut self, fsync: bool) -> Self {
+        self.fsync = Some(fsync);
+        self
+    }
+
+    /// Queue a `group.key = value` config override to write once the repo
+    /// has been opened or created.
+    pub fn config(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.push((key.into(), value.into()));
+        self
+    }
+
+    /// Open (or, with [`Self::create`] set, initialize) the repo at `path`
+    /// below `dfd`.
+    ///
+    /// Unlike [`Repo::checkout_at`], which passes `destination_dfd` straight
+    /// through to a dfd-relative C entry point, libostree has no dfd-relative
+    /// repo-open function to call here. This resolves `dfd` to a path via
+    /// `/proc/self/fd/<dfd>` and opens that, so it requires procfs to be
+    /// mounted and will fail with an I/O error where it isn't (some minimal
+    /// containers and chroots).
+    pub fn open_at(
+        self,
+        dfd: std::os::unix::io::RawFd,
+        path: impl AsRef<Path>,
+        cancellable: Option<&Cancellable>,
+    ) -> Result<Repo, Error> {
+        let full_path = Path::new(&format!("/proc/self/fd/{}", dfd)).join(path.as_ref());
+        let repo = Repo::new_for_path(&full_path);
+
+        if self.create {
+            repo.create(self.mode, cancellable)?;
+        } else {
+            repo.open(cancellable)?;
+        }
+
+        if self.fsync.is_some() || !self.config.is_empty() {
+            let key_file = repo.copy_config();
+            if let Some(fsync) = self.fsync {
+                key_file.set_boolean("core", "fsync", fsync);
+            }
+            for (dotted_key, value) in &self.config {
+                let (group, key) = dotted_key
+                    .split_once('.')
+                    .unwrap_or(("core", dotted_key.as_str()));
+                key_file.set_string(group, key, value);
+            }
+            repo.write_config(&key_file)?;
+        }
+
+        Ok(repo)
+    }
+}
@@ -0,0 +1,120 @@
+use gio::Cancellable;
+use glib::translate::*;
+use glib::Error;
+use ostree_sys as ffi;
+use std::ops::Deref;
+use std::ptr;
+
+use crate::Repo;
+
+/// RAII guard for an ostree repo transaction, obtained from
+/// [`Repo::auto_transaction`].
+///
+/// The guard derefs to the underlying [`Repo`] so the usual content/dirtree/
+/// dirmeta-writing methods can be called directly on it while the
+/// transaction is open. Dropping the guard without calling [`Self::commit`]
+/// aborts the transaction automatically, so an early return via `?` partway
+/// through a commit can't leave the repo in a half-written transactional
+/// state.
+pub struct RepoTransaction<'a> {
+    repo: &'a Repo,
+    cancellable: Option<&'a Cancellable>,
+    finished: bool,
+}
+
+impl Repo {
+    /// Open a transaction on this repo, returning a guard that aborts it on
+    /// drop unless [`RepoTransaction::commit`] (or
+    /// [`RepoTransaction::abort`]) is called first.
+    pub fn auto_transaction<'a>(
+        &'a self,
+        cancellable: Option<&'a Cancellable>,
+    ) -> Result<RepoTransaction<'a>, Error> {
+        unsafe {
+            let mut error = ptr::null_mut();
+            let is_ok = ffi::ostree_repo_prepare_transaction(
+                self.to_glib_none().0,
+                ptr::null_mut(),
+                cancellable.to_glib_none().0,
+                &mut error,
+            );
+            if is_ok == glib_sys::GFALSE {
+                return Err(from_glib_full(error));
+            }
+        }
+
+        Ok(RepoTransaction {
+            repo: self,
+            cancellable,
+            finished: false,
+        })
+    }
+}
+
+impl<'a> RepoTransaction<'a> {
+    /// Commit the transaction, consuming the guard.
+    pub fn commit(mut self) -> Result<(), Error> {
+        self.finished = true;
+        unsafe {
+            let mut error = ptr::null_mut();
+            let is_ok = ffi::ostree_repo_commit_transaction(
+                self.repo.to_glib_none().0,
+                ptr::null_mut(),
+                self.cancellable.to_glib_none().0,
+                &mut error,
+            );
+            if is_ok == glib_sys::GFALSE {
+                Err(from_glib_full(error))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    /// Abort the transaction explicitly, consuming the guard. Equivalent to
+    /// letting it drop, but lets the caller observe the error instead of
+    /// silently discarding it.
+    pub fn abort(mut self) -> Result<(), Error> {
+        self.finished = true;
+        unsafe {
+            let mut error = ptr::null_mut();
+            let is_ok = ffi::ostree_repo_abort_transaction(
+                self.repo.to_glib_none().0,
+                self.cancellable.to_glib_none().0,
+                &mut error,
+            );
+            if is_ok == glib_sys::GFALSE {
+                Err(from_glib_full(error))
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'a> Deref for RepoTransaction<'a> {
+    type Target = Repo;
+
+    fn deref(&self) -> &Repo {
+        self.repo
+    }
+}
+
+impl<'a> Drop for RepoTransaction<'a> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        unsafe {
+            // Best-effort: there's no way to propagate an error from Drop,
+            // and an abort failing here just means the next transaction
+            // attempt will surface the underlying problem.
+            let mut error = ptr::null_mut();
+            ffi::ostree_repo_abort_transaction(
+                self.repo.to_glib_none().0,
+                self.cancellable.to_glib_none().0,
+                &mut error,
+            );
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::util::*;
-use gio::NONE_CANCELLABLE;
+use gio::{File, NONE_CANCELLABLE};
 use ostree::*;
 use std::os::unix::io::AsRawFd;
 
@@ -34,7 +34,7 @@ fn should_checkout_at_with_default_options() {
     test_repo
         .repo
         .checkout_at(
-            Some(&RepoCheckoutAtOptions::default()),
+            Some(RepoCheckoutAtOptions::default()),
             dirfd.as_raw_fd(),
             "test-checkout",
             &checksum,
@@ -55,16 +55,17 @@ fn should_checkout_at_with_options() {
     test_repo
         .repo
         .checkout_at(
-            Some(&RepoCheckoutAtOptions {
-                mode: RepoCheckoutMode::User,
-                overwrite_mode: RepoCheckoutOverwriteMode::AddFiles,
-                enable_fsync: true,
-                force_copy: true,
-                force_copy_zerosized: true,
-                devino_to_csum_cache: Some(RepoDevInoCache::new()),
-                filter: repo_checkout_filter(|_repo, _path, _stat| RepoCheckoutFilterResult::Allow),
-                ..Default::default()
-            }),
+            Some(
+                RepoCheckoutAtOptions::builder()
+                    .mode(RepoCheckoutMode::User)
+                    .overwrite_mode(RepoCheckoutOverwriteMode::AddFiles)
+                    .enable_fsync(true)
+                    .force_copy(true)
+                    .force_copy_zerosized(true)
+                    .devino_cache(RepoDevInoCache::new())
+                    .filter(|_repo, _path, _stat| RepoCheckoutFilterResult::Allow)
+                    .build(),
+            ),
             dirfd.as_raw_fd(),
             "test-checkout",
             &checksum,
@@ -85,16 +86,17 @@ fn should_checkout_at_with_filter() {
     test_repo
         .repo
         .checkout_at(
-            Some(&RepoCheckoutAtOptions {
-                filter: repo_checkout_filter(|_repo, path, _stat| {
-                    if let Some("testfile") = path.file_name().map(|s| s.to_str().unwrap()) {
-                        RepoCheckoutFilterResult::Skip
-                    } else {
-                        RepoCheckoutFilterResult::Allow
-                    }
-                }),
-                ..Default::default()
-            }),
+            Some(
+                RepoCheckoutAtOptions::builder()
+                    .filter(|_repo, path, _stat| {
+                        if let Some("testfile") = path.file_name().map(|s| s.to_str().unwrap()) {
+                            RepoCheckoutFilterResult::Skip
+                        } else {
+                            RepoCheckoutFilterResult::Allow
+                        }
+                    })
+                    .build(),
+            ),
             dirfd.as_raw_fd(),
             "test-checkout",
             &checksum,
@@ -106,3 +108,192 @@ fn should_checkout_at_with_filter() {
     assert!(std::fs::read_dir(&testdir).is_ok());
     assert!(std::fs::File::open(&testdir.join("testfile")).is_err());
 }
+
+#[test]
+fn should_checkout_at_with_subpath() {
+    let test_repo = TestRepo::new();
+    let checksum = test_repo.test_commit("test");
+    let checkout_dir = tempfile::tempdir().expect("checkout dir");
+
+    let dirfd = openat::Dir::open(checkout_dir.path()).expect("openat");
+    test_repo
+        .repo
+        .checkout_at(
+            Some(RepoCheckoutAtOptions::builder().subpath("testdir").build()),
+            dirfd.as_raw_fd(),
+            "test-checkout",
+            &checksum,
+            NONE_CANCELLABLE,
+        )
+        .expect("checkout at");
+
+    let checkout_root = checkout_dir.path().join("test-checkout");
+    assert!(std::fs::File::open(checkout_root.join("testfile")).is_ok());
+    assert!(std::fs::read_dir(checkout_root.join("testdir")).is_err());
+}
+
+#[test]
+fn should_checkout_at_without_populating_uncompressed_cache() {
+    let test_repo = TestRepo::new();
+    let checksum = test_repo.test_commit("test");
+
+    let cache_dir = test_repo
+        .repo
+        .get_path()
+        .expect("repo path")
+        .join("uncompressed-objects-cache");
+
+    // Control: without the flag, a checkout of this repo/commit does
+    // populate the cache, so the assertion below isn't vacuous.
+    let control_checkout_dir = tempfile::tempdir().expect("checkout dir");
+    let control_dirfd = openat::Dir::open(control_checkout_dir.path()).expect("openat");
+    test_repo
+        .repo
+        .checkout_at(
+            None,
+            control_dirfd.as_raw_fd(),
+            "test-checkout",
+            &checksum,
+            NONE_CANCELLABLE,
+        )
+        .expect("checkout at");
+    assert!(
+        cache_dir.exists() && std::fs::read_dir(&cache_dir).unwrap().next().is_some(),
+        "expected the control checkout to populate the uncompressed-objects cache"
+    );
+    std::fs::remove_dir_all(&cache_dir).expect("reset uncompressed-objects cache");
+
+    let checkout_dir = tempfile::tempdir().expect("checkout dir");
+    let dirfd = openat::Dir::open(checkout_dir.path()).expect("openat");
+    test_repo
+        .repo
+        .checkout_at(
+            Some(
+                RepoCheckoutAtOptions::builder()
+                    .no_copy_fallback(true)
+                    .build(),
+            ),
+            dirfd.as_raw_fd(),
+            "test-checkout",
+            &checksum,
+            NONE_CANCELLABLE,
+        )
+        .expect("checkout at");
+
+    assert_test_file(checkout_dir.path());
+    assert!(!cache_dir.exists() || std::fs::read_dir(&cache_dir).unwrap().next().is_none());
+}
+
+#[test]
+fn should_checkout_at_with_union_identical_strategy() {
+    let test_repo = TestRepo::new();
+    let checksum = test_repo.test_commit("test");
+    let checkout_dir = tempfile::tempdir().expect("checkout dir");
+    let dirfd = openat::Dir::open(checkout_dir.path()).expect("openat");
+
+    for _ in 0..2 {
+        test_repo
+            .repo
+            .checkout_at(
+                Some(
+                    RepoCheckoutAtOptions::builder()
+                        .strategy(RepoCheckoutStrategy::UNION_IDENTICAL)
+                        .build(),
+                ),
+                dirfd.as_raw_fd(),
+                "test-checkout",
+                &checksum,
+                NONE_CANCELLABLE,
+            )
+            .expect("checkout at");
+    }
+
+    assert_test_file(checkout_dir.path());
+}
+
+/// Commit a tree containing a single char-special (0,0) entry at the
+/// relative path `name` — the overlayfs-style marker libostree's whiteout
+/// processing looks for — and return the resulting checksum.
+fn commit_whiteout_tree(test_repo: &TestRepo, name: &str) -> String {
+    let content_dir = tempfile::tempdir().expect("content dir");
+    let whiteout_path = content_dir.path().join(name);
+    std::fs::create_dir_all(whiteout_path.parent().unwrap()).expect("create marker parent dir");
+    let c_path = std::ffi::CString::new(whiteout_path.to_str().unwrap()).unwrap();
+    let rc = unsafe { libc::mknod(c_path.as_ptr(), libc::S_IFCHR, 0) };
+    assert_eq!(rc, 0, "mknod whiteout marker");
+
+    let txn = test_repo
+        .repo
+        .auto_transaction(NONE_CANCELLABLE)
+        .expect("prepare transaction");
+
+    let mtree = ostree::MutableTree::new();
+    txn.write_directory_to_mtree(
+        &File::new_for_path(content_dir.path()),
+        &mtree,
+        None,
+        NONE_CANCELLABLE,
+    )
+    .expect("write directory to mtree");
+    let (root, _) = txn
+        .write_mtree(&mtree, NONE_CANCELLABLE)
+        .expect("write mtree");
+    let checksum = txn
+        .write_commit(None, None, None, None, &root, NONE_CANCELLABLE)
+        .expect("write commit");
+    txn.commit().expect("commit transaction");
+
+    checksum.to_string()
+}
+
+#[test]
+fn should_checkout_at_with_whiteout_processing_strategy() {
+    let test_repo = TestRepo::new();
+    let checksum = test_repo.test_commit("test");
+    let checkout_dir = tempfile::tempdir().expect("checkout dir");
+    let dirfd = openat::Dir::open(checkout_dir.path()).expect("openat");
+
+    // First lay down the plain "testfile" like every other test here.
+    test_repo
+        .repo
+        .checkout_at(
+            None,
+            dirfd.as_raw_fd(),
+            "test-checkout",
+            &checksum,
+            NONE_CANCELLABLE,
+        )
+        .expect("checkout at");
+    let testfile = checkout_dir
+        .path()
+        .join("test-checkout")
+        .join("testdir")
+        .join("testfile");
+    assert!(testfile.exists());
+
+    // Then union-checkout a tree whose only file is a whiteout marker for
+    // "testdir/testfile" and confirm the shadowed destination file is removed.
+    let whiteout_checksum = commit_whiteout_tree(&test_repo, "testdir/testfile");
+    test_repo
+        .repo
+        .checkout_at(
+            Some(
+                RepoCheckoutAtOptions::builder()
+                    .strategy(
+                        RepoCheckoutStrategy::UNION_IDENTICAL
+                            | RepoCheckoutStrategy::PROCESS_WHITEOUTS,
+                    )
+                    .build(),
+            ),
+            dirfd.as_raw_fd(),
+            "test-checkout",
+            &whiteout_checksum,
+            NONE_CANCELLABLE,
+        )
+        .expect("checkout at");
+
+    assert!(
+        !testfile.exists(),
+        "expected the whiteout-processing checkout to remove the shadowed file"
+    );
+}
@@ -0,0 +1,73 @@
+use gio::NONE_CANCELLABLE;
+use ostree::*;
+use std::os::unix::io::AsRawFd;
+
+#[test]
+fn should_create_then_reopen_repo() {
+    let repo_dir = tempfile::tempdir().expect("repo dir");
+    let dirfd = openat::Dir::open(repo_dir.path()).expect("openat");
+
+    let created = RepoOpener::new()
+        .mode(RepoMode::BareUser)
+        .create(true)
+        .open_at(dirfd.as_raw_fd(), "repo", NONE_CANCELLABLE)
+        .expect("create repo");
+    assert_eq!(created.get_mode(), RepoMode::BareUser);
+
+    let reopened = RepoOpener::new()
+        .open_at(dirfd.as_raw_fd(), "repo", NONE_CANCELLABLE)
+        .expect("reopen repo");
+    assert_eq!(reopened.get_mode(), RepoMode::BareUser);
+}
+
+#[test]
+fn should_ignore_mode_when_reopening_existing_repo() {
+    let repo_dir = tempfile::tempdir().expect("repo dir");
+    let dirfd = openat::Dir::open(repo_dir.path()).expect("openat");
+
+    RepoOpener::new()
+        .mode(RepoMode::Bare)
+        .create(true)
+        .open_at(dirfd.as_raw_fd(), "repo", NONE_CANCELLABLE)
+        .expect("create repo");
+
+    // `create` is false here, so `mode` must be ignored in favor of
+    // whatever the repo was actually initialized with above.
+    let reopened = RepoOpener::new()
+        .mode(RepoMode::BareUser)
+        .open_at(dirfd.as_raw_fd(), "repo", NONE_CANCELLABLE)
+        .expect("reopen repo");
+
+    assert_eq!(reopened.get_mode(), RepoMode::Bare);
+}
+
+#[test]
+fn should_write_config_overrides_at_open_time() {
+    let repo_dir = tempfile::tempdir().expect("repo dir");
+    let dirfd = openat::Dir::open(repo_dir.path()).expect("openat");
+
+    let repo = RepoOpener::new()
+        .mode(RepoMode::BareUser)
+        .create(true)
+        .fsync(false)
+        .config("core.compression-level", "9")
+        .config("compression-level-without-a-group", "5")
+        .open_at(dirfd.as_raw_fd(), "repo", NONE_CANCELLABLE)
+        .expect("create repo");
+
+    let config = repo.copy_config();
+    assert_eq!(config.get_boolean("core", "fsync").expect("fsync"), false);
+    assert_eq!(
+        config
+            .get_string("core", "compression-level")
+            .expect("compression-level"),
+        "9"
+    );
+    // A bare key with no `group.` prefix falls back to the "core" group.
+    assert_eq!(
+        config
+            .get_string("core", "compression-level-without-a-group")
+            .expect("compression-level-without-a-group"),
+        "5"
+    );
+}
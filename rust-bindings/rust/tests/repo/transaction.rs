@@ -0,0 +1,51 @@
+use crate::util::*;
+use gio::{File, NONE_CANCELLABLE};
+use ostree::*;
+
+#[test]
+fn should_abort_transaction_on_drop_without_commit() {
+    let test_repo = TestRepo::new();
+
+    let content_dir = tempfile::tempdir().expect("content dir");
+    std::fs::write(content_dir.path().join("abandoned"), b"never committed")
+        .expect("write content");
+
+    let abandoned_checksum = {
+        let txn = test_repo
+            .repo
+            .auto_transaction(NONE_CANCELLABLE)
+            .expect("prepare transaction");
+
+        let mtree = MutableTree::new();
+        txn.write_directory_to_mtree(
+            &File::new_for_path(content_dir.path()),
+            &mtree,
+            None,
+            NONE_CANCELLABLE,
+        )
+        .expect("write directory to mtree");
+        let (root, _) = txn
+            .write_mtree(&mtree, NONE_CANCELLABLE)
+            .expect("write mtree");
+        let checksum = txn
+            .write_commit(None, None, None, None, &root, NONE_CANCELLABLE)
+            .expect("write commit");
+
+        // `txn` is dropped here without calling `.commit()` or `.abort()`,
+        // which should abort the transaction and discard `checksum`.
+        checksum.to_string()
+    };
+
+    assert!(
+        test_repo.repo.load_commit(&abandoned_checksum).is_err(),
+        "expected the dropped transaction's commit to never become visible"
+    );
+
+    // A fresh transaction should be free to proceed normally afterwards,
+    // proving the abandoned one didn't leave the repo locked or half-written.
+    let txn = test_repo
+        .repo
+        .auto_transaction(NONE_CANCELLABLE)
+        .expect("prepare transaction after an aborted drop");
+    txn.commit().expect("commit empty transaction");
+}